@@ -7,7 +7,7 @@
 //! let mut cmos = ReadRTC::new(0x00, 0x00);
 //! let time: Time = cmos.read();
 //! ```
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 use core::cmp::Ordering;
 use x86_64::instructions::port::Port;
 
@@ -17,6 +17,18 @@ const CMOS_ADDRESS: u16 = 0x70;
 /// Data receiving port
 const CMOS_DATA: u16 = 0x71;
 
+/// Converts a BCD-encoded byte into its binary value
+#[must_use]
+pub fn bcd_to_bin(bcd: u8) -> u8 {
+    (bcd & 0x0F) + ((bcd / 16) * 10)
+}
+
+/// Converts a binary value in the range 0–99 into its BCD representation
+#[must_use]
+pub fn bin_to_bcd(bin: u8) -> u8 {
+    ((bin / 10) << 4) | (bin % 10)
+}
+
 /// Struct for storage time
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 pub struct Time {
@@ -25,8 +37,127 @@ pub struct Time {
     pub hour: u8,
     pub day: u8,
     pub month: u8,
-    pub year: u8,
+    /// Full four-digit year, e.g. `2024`
+    pub year: u32,
+    /// Raw century digits as decoded from the RTC's century register (`0` if none is configured)
     pub century: u8,
+    pub weekday: u8,
+}
+
+/// Number of days in each month of a non-leap year
+const DAYS_IN_MONTH: [u64; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+
+/// Tells whether `year` is a leap year in the Gregorian calendar
+fn is_leap_year(year: u64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Number of days in `month` (1-12) of `year`
+fn days_in_month(month: u8, year: u64) -> u64 {
+    let mut days = DAYS_IN_MONTH[(month - 1) as usize];
+
+    if month == 2 && is_leap_year(year) {
+        days += 1;
+    }
+
+    days
+}
+
+impl Time {
+    /// Converts this `Time` to the number of seconds since the Unix epoch (1970-01-01 00:00:00 UTC)
+    #[must_use]
+    pub fn to_unix_timestamp(&self) -> u64 {
+        let year = u64::from(self.year);
+
+        let mut days: u64 = 0;
+
+        for y in 1970..year {
+            days += if is_leap_year(y) { 366 } else { 365 };
+        }
+
+        for m in 1..self.month {
+            days += days_in_month(m, year);
+        }
+
+        days += u64::from(self.day).saturating_sub(1);
+
+        days * 86400
+            + u64::from(self.hour) * 3600
+            + u64::from(self.minute) * 60
+            + u64::from(self.second)
+    }
+
+    /// Builds a `Time` from the number of seconds since the Unix epoch (1970-01-01 00:00:00 UTC)
+    #[must_use]
+    pub fn from_unix_timestamp(timestamp: u64) -> Time {
+        let second = (timestamp % 60) as u8;
+        let minute = ((timestamp / 60) % 60) as u8;
+        let hour = ((timestamp / 3600) % 24) as u8;
+        let mut days = timestamp / 86400;
+
+        let mut year: u64 = 1970;
+
+        loop {
+            let year_days = if is_leap_year(year) { 366 } else { 365 };
+
+            if days < year_days {
+                break;
+            }
+
+            days -= year_days;
+            year += 1;
+        }
+
+        let mut month: u8 = 1;
+
+        loop {
+            let month_days = days_in_month(month, year);
+
+            if days < month_days {
+                break;
+            }
+
+            days -= month_days;
+            month += 1;
+        }
+
+        let day = (days + 1) as u8;
+
+        Time {
+            second,
+            minute,
+            hour,
+            day,
+            month,
+            year: year as u32,
+            century: (year / 100) as u8,
+            weekday: 0,
+        }
+    }
+
+    /// Derives the day of the week (1 = Sunday … 7 = Saturday) from the date via Zeller's
+    /// congruence
+    ///
+    /// Useful as a fallback on hardware that leaves the RTC's day-of-week register unpopulated.
+    #[must_use]
+    pub fn compute_weekday(&self) -> u8 {
+        let year_full = u64::from(self.year);
+        let (month, year) = if self.month < 3 {
+            (u64::from(self.month) + 12, year_full - 1)
+        } else {
+            (u64::from(self.month), year_full)
+        };
+
+        let k = year % 100;
+        let j = year / 100;
+        let day = u64::from(self.day);
+
+        let h = (day + (13 * (month + 1)) / 5 + k + k / 4 + j / 4 + 5 * j) % 7;
+
+        // Zeller's congruence returns 0 = Saturday … 6 = Friday; remap to 1 = Sunday … 7 =
+        // Saturday
+        (((h + 6) % 7) + 1) as u8
+    }
 }
 
 impl PartialOrd for Time {
@@ -52,14 +183,16 @@ impl Ord for Time {
 pub struct ReadRTC {
     cmos_address: Port<u8>,
     cmos_data: Port<u8>,
-    current_year: u8,
+    /// Full four-digit year hint (e.g. `2024`), used to disambiguate the century when no century
+    /// register is configured
+    current_year: u32,
     century_register: u8,
 }
 
 impl ReadRTC {
     /// Creates a new `ReadRTC`.
     #[must_use]
-    pub const fn new(current_year: u8, century_register: u8) -> ReadRTC {
+    pub const fn new(current_year: u32, century_register: u8) -> ReadRTC {
         ReadRTC {
             cmos_address: Port::new(CMOS_ADDRESS),
             cmos_data: Port::new(CMOS_DATA),
@@ -68,6 +201,22 @@ impl ReadRTC {
         }
     }
 
+    /// Creates a new `ReadRTC` using the century register offset parsed from the ACPI FADT's
+    /// `Century` field
+    ///
+    /// Pass the byte the caller extracted while parsing the FADT; if ACPI reports no century
+    /// register (offset `0`), the century is instead inferred from `current_year` on
+    /// [`read`](ReadRTC::read).
+    #[must_use]
+    pub const fn with_acpi(current_year: u32, fadt_century_offset: u8) -> ReadRTC {
+        ReadRTC::new(current_year, fadt_century_offset)
+    }
+
+    /// Sets the century register after the fact, e.g. once ACPI table parsing has completed
+    pub fn set_century_register(&mut self, reg: u8) {
+        self.century_register = reg;
+    }
+
     /// Lets you know if a time update is in progress
     fn get_update_in_progress_flag(&mut self) -> u8 {
         unsafe {
@@ -84,28 +233,55 @@ impl ReadRTC {
         }
     }
 
-    /// Updating our time
-    fn update_time(&mut self) -> Time {
-        // Make sure an update isn't in progress
-        while self.get_update_in_progress_flag() != 0 {}
+    /// Sets a value in a time register
+    fn set_rtc_register(&mut self, reg: u8, value: u8) {
+        unsafe {
+            self.cmos_address.write(reg);
+            self.cmos_data.write(value);
+        }
+    }
 
-        Time {
-            second: self.get_rtc_register(0x00),
-            minute: self.get_rtc_register(0x02),
-            hour: self.get_rtc_register(0x04),
-            day: self.get_rtc_register(0x07),
-            month: self.get_rtc_register(0x08),
-            year: self.get_rtc_register(0x09),
-            century: if self.century_register == 0 {
-                0
-            } else {
-                self.get_rtc_register(self.century_register)
-            },
+    /// Enables periodic interrupts on IRQ8, selecting a frequency of `32768 >> (rate - 1)` Hz
+    /// (2 Hz–8192 Hz) by writing `rate` (3–15) into the low nibble of status register A, then
+    /// setting bit 0x40 of status register B
+    ///
+    /// The caller is responsible for installing the IRQ8 handler and calling
+    /// [`ReadRTC::acknowledge`] inside it; failing to read register C there leaves the interrupt
+    /// line latched and no further interrupts will fire.
+    pub fn enable_periodic_interrupt(&mut self, rate: u8) -> Result<(), OutOfRangeError> {
+        if !(3..=15).contains(&rate) {
+            return Err(OutOfRangeError);
         }
+
+        let register_a = self.get_rtc_register(0x0A);
+        self.set_rtc_register(0x0A, (register_a & 0xF0) | rate);
+
+        let register_b = self.get_rtc_register(0x0B);
+        self.set_rtc_register(0x0B, register_b | 0x40);
+
+        Ok(())
     }
 
-    /// Gets the time without regard to the time zone
-    pub fn read(&mut self) -> Time {
+    /// Clears the periodic interrupt flag in status register C so the next interrupt can fire
+    pub fn acknowledge(&mut self) {
+        self.get_rtc_register(0x0C);
+    }
+
+    /// Reads the time without decoding BCD, 12-hour or timezone-less assumptions, returning the
+    /// raw register bytes alongside status register B
+    ///
+    /// Useful on non-standard hardware (e.g. chips that always store binary) that needs to apply
+    /// its own decoding instead of the one [`read`](ReadRTC::read) assumes.
+    pub fn read_raw(&mut self) -> (Time, u8) {
+        let time = self.stable_time();
+        let register_b = self.get_rtc_register(0x0B);
+
+        (time, register_b)
+    }
+
+    /// Repeatedly reads the time registers until two consecutive reads agree, to avoid sampling
+    /// mid-update
+    fn stable_time(&mut self) -> Time {
         let mut last_time: Time;
         let mut time: Time = self.update_time();
 
@@ -120,24 +296,52 @@ impl ReadRTC {
                 && (last_time.month == time.month)
                 && (last_time.year == time.year)
                 && (last_time.century == time.century)
+                && (last_time.weekday == time.weekday)
             {
                 break;
             }
         }
 
+        time
+    }
+
+    /// Updating our time
+    fn update_time(&mut self) -> Time {
+        // Make sure an update isn't in progress
+        while self.get_update_in_progress_flag() != 0 {}
+
+        Time {
+            second: self.get_rtc_register(0x00),
+            minute: self.get_rtc_register(0x02),
+            hour: self.get_rtc_register(0x04),
+            day: self.get_rtc_register(0x07),
+            month: self.get_rtc_register(0x08),
+            year: u32::from(self.get_rtc_register(0x09)),
+            century: if self.century_register == 0 {
+                0
+            } else {
+                self.get_rtc_register(self.century_register)
+            },
+            weekday: self.get_rtc_register(0x06),
+        }
+    }
+
+    /// Gets the time without regard to the time zone
+    pub fn read(&mut self) -> Time {
+        let mut time = self.stable_time();
         let register_b = self.get_rtc_register(0x0B);
 
         if register_b & 0x04 == 0 {
-            time.second = (time.second & 0x0F) + ((time.second / 16) * 10);
-            time.minute = (time.minute & 0x0F) + ((time.minute / 16) * 10);
-            time.hour =
-                ((time.hour & 0x0F) + (((time.hour & 0x70) / 16) * 10)) | (time.hour & 0x80);
-            time.day = (time.day & 0x0F) + ((time.day / 16) * 10);
-            time.month = (time.month & 0x0F) + ((time.month / 16) * 10);
-            time.year = (time.year & 0x0F) + ((time.year / 16) * 10);
+            time.second = bcd_to_bin(time.second);
+            time.minute = bcd_to_bin(time.minute);
+            time.hour = bcd_to_bin(time.hour & 0x7F) | (time.hour & 0x80);
+            time.day = bcd_to_bin(time.day);
+            time.month = bcd_to_bin(time.month);
+            time.year = u32::from(bcd_to_bin(time.year as u8));
+            time.weekday = bcd_to_bin(time.weekday);
 
             if self.century_register != 0 {
-                time.century = (time.century & 0x0F) + ((time.century / 16) * 10);
+                time.century = bcd_to_bin(time.century);
             }
         }
 
@@ -146,17 +350,225 @@ impl ReadRTC {
             time.hour = ((time.hour & 0x7F) + 12) % 24;
         }
 
-        // Calculate the full (4-digit) year
-        if self.century_register == 0 {
-            time.year += (self.current_year / 100) * 100;
+        // Calculate the full (four-digit) year from the decoded two-digit year, widening to u32
+        // first so folding in the century can't overflow
+        time.year = if self.century_register == 0 {
+            let mut year = time.year + (self.current_year / 100) * 100;
 
-            if time.year < self.current_year {
-                time.year += 100;
-            };
+            if year < self.current_year {
+                year += 100;
+            }
+
+            year
         } else {
-            time.year += time.century * 100;
+            u32::from(time.century) * 100 + time.year
+        };
+
+        // Hardware that leaves the day-of-week register unpopulated reads back 0
+        if time.weekday == 0 {
+            time.weekday = time.compute_weekday();
         }
 
         time
     }
 }
+
+/// Error returned when a `Time` field is too large to be encoded in BCD (0–99), or when a
+/// periodic interrupt rate lies outside 3–15
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct OutOfRangeError;
+
+/// Converts a 24-hour `hour` (0–23) into its 12-hour value and the PM bit (`0x80` if PM, else `0`)
+/// to be OR'd into the BCD-encoded hour register
+fn encode_12_hour(hour: u8) -> (u8, u8) {
+    if hour == 0 {
+        (12, 0)
+    } else if hour == 12 {
+        (12, 0x80)
+    } else if hour > 12 {
+        (hour - 12, 0x80)
+    } else {
+        (hour, 0)
+    }
+}
+
+/// Struct for writing time, storage ports and century register
+pub struct WriteRTC {
+    cmos_address: Port<u8>,
+    cmos_data: Port<u8>,
+    century_register: u8,
+}
+
+impl WriteRTC {
+    /// Creates a new `WriteRTC`.
+    #[must_use]
+    pub const fn new(century_register: u8) -> WriteRTC {
+        WriteRTC {
+            cmos_address: Port::new(CMOS_ADDRESS),
+            cmos_data: Port::new(CMOS_DATA),
+            century_register,
+        }
+    }
+
+    /// Retrieves a value from a time register
+    fn get_rtc_register(&mut self, reg: u8) -> u8 {
+        unsafe {
+            self.cmos_address.write(reg);
+            self.cmos_data.read()
+        }
+    }
+
+    /// Sets a value in a time register
+    fn set_rtc_register(&mut self, reg: u8, value: u8) {
+        unsafe {
+            self.cmos_address.write(reg);
+            self.cmos_data.write(value);
+        }
+    }
+
+    /// Programs the RTC registers with the given time
+    ///
+    /// Reads status register B first to learn whether the chip expects BCD
+    /// or binary values and 12 or 24 hour mode, sets the SET bit to halt
+    /// updates while writing, then clears it again once every field has
+    /// been written.
+    pub fn write(&mut self, time: &Time) -> Result<(), OutOfRangeError> {
+        if time.second > 99
+            || time.minute > 99
+            || time.hour > 99
+            || time.day > 99
+            || time.month > 99
+            || time.year > 9999
+        {
+            return Err(OutOfRangeError);
+        }
+
+        // The RTC only has two BCD digits each for the year and century registers
+        let year_ones = (time.year % 100) as u8;
+        let year_hundreds = (time.year / 100) as u8;
+
+        let register_b = self.get_rtc_register(0x0B);
+        let bcd_mode = register_b & 0x04 == 0;
+        let twelve_hour_mode = register_b & 0x02 == 0;
+
+        let (hour, pm_bit) = if twelve_hour_mode {
+            encode_12_hour(time.hour)
+        } else {
+            (time.hour, 0)
+        };
+
+        let second = if bcd_mode { bin_to_bcd(time.second) } else { time.second };
+        let minute = if bcd_mode { bin_to_bcd(time.minute) } else { time.minute };
+        let hour = (if bcd_mode { bin_to_bcd(hour) } else { hour }) | pm_bit;
+        let day = if bcd_mode { bin_to_bcd(time.day) } else { time.day };
+        let month = if bcd_mode { bin_to_bcd(time.month) } else { time.month };
+        let year = if bcd_mode { bin_to_bcd(year_ones) } else { year_ones };
+        let century = if bcd_mode { bin_to_bcd(year_hundreds) } else { year_hundreds };
+
+        // Set the SET bit to stop the chip from updating the registers while we write them
+        self.set_rtc_register(0x0B, register_b | 0x80);
+
+        self.set_rtc_register(0x00, second);
+        self.set_rtc_register(0x02, minute);
+        self.set_rtc_register(0x04, hour);
+        self.set_rtc_register(0x07, day);
+        self.set_rtc_register(0x08, month);
+        self.set_rtc_register(0x09, year);
+
+        if self.century_register != 0 {
+            self.set_rtc_register(self.century_register, century);
+        }
+
+        // Clear the SET bit so the chip resumes normal updates
+        self.set_rtc_register(0x0B, register_b);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_12_hour_handles_noon_and_midnight() {
+        assert_eq!(encode_12_hour(0), (12, 0));
+        assert_eq!(encode_12_hour(12), (12, 0x80));
+        assert_eq!(encode_12_hour(13), (1, 0x80));
+        assert_eq!(encode_12_hour(23), (11, 0x80));
+        assert_eq!(encode_12_hour(1), (1, 0));
+        assert_eq!(encode_12_hour(11), (11, 0));
+    }
+
+    #[test]
+    fn to_unix_timestamp_matches_a_known_date() {
+        let time = Time {
+            second: 30,
+            minute: 15,
+            hour: 14,
+            day: 25,
+            month: 6,
+            year: 2024,
+            century: 20,
+            weekday: 0,
+        };
+
+        assert_eq!(time.to_unix_timestamp(), 1_719_324_930);
+    }
+
+    #[test]
+    fn time_round_trips_through_a_unix_timestamp() {
+        let timestamp = 1_719_324_930;
+        let time = Time::from_unix_timestamp(timestamp);
+
+        assert_eq!(time.year, 2024);
+        assert_eq!(time.month, 6);
+        assert_eq!(time.day, 25);
+        assert_eq!(time.hour, 14);
+        assert_eq!(time.minute, 15);
+        assert_eq!(time.second, 30);
+        assert_eq!(time.to_unix_timestamp(), timestamp);
+    }
+
+    #[test]
+    fn to_unix_timestamp_handles_a_leap_day() {
+        let time = Time {
+            second: 0,
+            minute: 0,
+            hour: 0,
+            day: 29,
+            month: 2,
+            year: 2024,
+            century: 20,
+            weekday: 0,
+        };
+
+        assert_eq!(Time::from_unix_timestamp(time.to_unix_timestamp()).day, 29);
+    }
+
+    #[test]
+    fn to_unix_timestamp_does_not_panic_on_a_zero_day() {
+        let _ = Time::default().to_unix_timestamp();
+    }
+
+    #[test]
+    fn compute_weekday_matches_known_dates() {
+        // 2024-06-25 was a Tuesday
+        let tuesday = Time {
+            day: 25,
+            month: 6,
+            year: 2024,
+            ..Time::default()
+        };
+        assert_eq!(tuesday.compute_weekday(), 3);
+
+        // 2000-01-01 was a Saturday
+        let saturday = Time {
+            day: 1,
+            month: 1,
+            year: 2000,
+            ..Time::default()
+        };
+        assert_eq!(saturday.compute_weekday(), 7);
+    }
+}